@@ -0,0 +1,193 @@
+/*
+*
+*    Copyright (c) 2020-2022 Project CHIP Authors
+*
+*    Licensed under the Apache License, Version 2.0 (the "License");
+*    you may not use this file except in compliance with the License.
+*    You may obtain a copy of the License at
+*
+*        http://www.apache.org/licenses/LICENSE-2.0
+*
+*    Unless required by applicable law or agreed to in writing, software
+*    distributed under the License is distributed on an "AS IS" BASIS,
+*    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*    See the License for the specific language governing permissions and
+*    limitations under the License.
+*/
+
+//! The Color Control cluster (`0x0300`), Hue/Saturation only: `CurrentHue`/`CurrentSaturation`
+//! (both 0-254, mapping onto 0-360 degrees / 0-100% the same way the Matter spec's other 8-bit
+//! color attributes do) plus a fixed `ColorMode` of `CurrentHueAndCurrentSaturation`.
+
+use std::cell::Cell;
+
+use rs_matter::attribute_enum;
+use rs_matter::data_model::objects::{
+    Access, AttrType, Attribute, Cluster, Handler, Quality,
+};
+use rs_matter::error::{Error, ErrorCode};
+use rs_matter::tlv::{FromTLV, TLVElement};
+use rs_matter::transport::exchange::Exchange;
+
+use rs_matter::data_model::objects::{
+    AttrDataEncoder, AttrDetails, ChangeNotifier, CmdDataEncoder, CmdDetails, Dataver,
+    NonBlockingHandler, ATTRIBUTE_LIST, FEATURE_MAP,
+};
+
+use strum::{EnumDiscriminants, FromRepr};
+
+pub const ID: u32 = 0x0300;
+
+pub const CMD_MOVE_TO_HUE_AND_SATURATION: u16 = 0x06;
+
+/// `ColorMode`/`EnhancedColorMode` value for "Current hue and current saturation".
+const COLOR_MODE_HUE_SATURATION: u8 = 0;
+
+#[derive(FromTLV)]
+pub struct MoveToHueAndSaturationRequest {
+    pub hue: u8,
+    pub saturation: u8,
+    pub transition_time: Option<u16>,
+}
+
+#[derive(FromRepr, EnumDiscriminants)]
+#[repr(u16)]
+pub enum Attributes {
+    CurrentHue(AttrType<u8>) = 0x0,
+    CurrentSaturation(AttrType<u8>) = 0x1,
+    ColorMode(AttrType<u8>) = 0x8,
+}
+
+attribute_enum!(Attributes);
+
+pub const CURRENT_HUE: Attribute = Attribute::new(
+    AttributesDiscriminants::CurrentHue as _,
+    Access::RV,
+    Quality::from_bits(Quality::PERSISTENT.bits()).unwrap(),
+);
+pub const CURRENT_SATURATION: Attribute = Attribute::new(
+    AttributesDiscriminants::CurrentSaturation as _,
+    Access::RV,
+    Quality::from_bits(Quality::PERSISTENT.bits()).unwrap(),
+);
+pub const COLOR_MODE: Attribute =
+    Attribute::new(AttributesDiscriminants::ColorMode as _, Access::RV, Quality::NONE);
+
+/// Hue/Saturation feature bit (`ColorControl.FeatureMap` bit 0): advertised so ecosystem
+/// controllers (Apple Home, Google Home, Alexa) recognize this as a color-capable light instead
+/// of treating `MoveToHueAndSaturation` as unsupported.
+const FEATURE_HUE_SATURATION: u32 = 0x1;
+
+pub const CLUSTER: Cluster<'static> = Cluster {
+    id: ID as _,
+    feature_map: FEATURE_HUE_SATURATION,
+    attributes: &[
+        FEATURE_MAP,
+        ATTRIBUTE_LIST,
+        CURRENT_HUE,
+        CURRENT_SATURATION,
+        COLOR_MODE,
+    ],
+    commands: &[CMD_MOVE_TO_HUE_AND_SATURATION],
+};
+
+pub struct ColorControlCluster {
+    data_ver: Dataver,
+    hue: Cell<u8>,
+    saturation: Cell<u8>,
+}
+
+impl ColorControlCluster {
+    pub const fn new(data_ver: Dataver) -> Self {
+        Self {
+            data_ver,
+            hue: Cell::new(0),
+            saturation: Cell::new(0),
+        }
+    }
+
+    /// Current hue/saturation, both 0-254.
+    pub fn current_hue_saturation(&self) -> (u8, u8) {
+        (self.hue.get(), self.saturation.get())
+    }
+
+    pub fn set_hue_saturation(&self, hue: u8, saturation: u8) {
+        if self.hue.get() != hue || self.saturation.get() != saturation {
+            self.hue.set(hue);
+            self.saturation.set(saturation);
+            self.data_ver.changed();
+        }
+    }
+
+    pub fn read(
+        &self,
+        _exchange: &Exchange,
+        attr: &AttrDetails,
+        encoder: AttrDataEncoder,
+    ) -> Result<(), Error> {
+        if let Some(writer) = encoder.with_dataver(self.data_ver.get())? {
+            if attr.is_system() {
+                CLUSTER.read(attr.attr_id, writer)
+            } else {
+                match attr.attr_id.try_into()? {
+                    Attributes::CurrentHue(codec) => codec.encode(writer, self.hue.get()),
+                    Attributes::CurrentSaturation(codec) => {
+                        codec.encode(writer, self.saturation.get())
+                    }
+                    Attributes::ColorMode(codec) => {
+                        codec.encode(writer, COLOR_MODE_HUE_SATURATION)
+                    }
+                }
+            }
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn invoke(
+        &self,
+        _exchange: &Exchange,
+        cmd: &CmdDetails,
+        data: &TLVElement,
+        _encoder: CmdDataEncoder,
+    ) -> Result<(), Error> {
+        match cmd.cmd_id {
+            CMD_MOVE_TO_HUE_AND_SATURATION => {
+                let request = MoveToHueAndSaturationRequest::from_tlv(data)?;
+                self.set_hue_saturation(request.hue, request.saturation);
+            }
+            _ => Err(ErrorCode::CommandNotFound)?,
+        }
+
+        Ok(())
+    }
+}
+
+impl Handler for ColorControlCluster {
+    fn read(
+        &self,
+        exchange: &Exchange,
+        attr: &AttrDetails,
+        encoder: AttrDataEncoder,
+    ) -> Result<(), Error> {
+        ColorControlCluster::read(self, exchange, attr, encoder)
+    }
+
+    fn invoke(
+        &self,
+        exchange: &Exchange,
+        cmd: &CmdDetails,
+        data: &TLVElement,
+        encoder: CmdDataEncoder,
+    ) -> Result<(), Error> {
+        ColorControlCluster::invoke(self, exchange, cmd, data, encoder)
+    }
+}
+
+impl NonBlockingHandler for ColorControlCluster {}
+
+impl ChangeNotifier<()> for ColorControlCluster {
+    fn consume_change(&mut self) -> Option<()> {
+        self.data_ver.consume_change(())
+    }
+}
@@ -0,0 +1,190 @@
+/*
+*
+*    Copyright (c) 2020-2022 Project CHIP Authors
+*
+*    Licensed under the Apache License, Version 2.0 (the "License");
+*    you may not use this file except in compliance with the License.
+*    You may obtain a copy of the License at
+*
+*        http://www.apache.org/licenses/LICENSE-2.0
+*
+*    Unless required by applicable law or agreed to in writing, software
+*    distributed under the License is distributed on an "AS IS" BASIS,
+*    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*    See the License for the specific language governing permissions and
+*    limitations under the License.
+*/
+
+//! The Level Control cluster (`0x0008`), driving `CurrentLevel` (0-254) for a dimmable light.
+//!
+//! `MoveToLevel`/`Step` are applied immediately rather than animated over the requested
+//! transition time, since the device loop only samples `current_level()` on its own 100ms
+//! cadence; a future revision could drive a real ramp off that same tick. `Move` needs that same
+//! running-transition support to mean anything, so it's rejected with `InvalidCommand` rather
+//! than silently doing nothing.
+
+use std::cell::Cell;
+
+use rs_matter::attribute_enum;
+use rs_matter::data_model::objects::{
+    Access, AttrType, Attribute, Cluster, Handler, Quality,
+};
+use rs_matter::error::{Error, ErrorCode};
+use rs_matter::tlv::{FromTLV, TLVElement};
+use rs_matter::transport::exchange::Exchange;
+
+use rs_matter::data_model::objects::{
+    AttrDataEncoder, AttrDetails, ChangeNotifier, CmdDataEncoder, CmdDetails, Dataver,
+    NonBlockingHandler, ATTRIBUTE_LIST, FEATURE_MAP,
+};
+
+use strum::{EnumDiscriminants, FromRepr};
+
+pub const ID: u32 = 0x0008;
+
+pub const CMD_MOVE_TO_LEVEL: u16 = 0x00;
+pub const CMD_MOVE: u16 = 0x01;
+pub const CMD_STEP: u16 = 0x02;
+pub const CMD_STOP: u16 = 0x03;
+
+#[derive(FromTLV)]
+pub struct MoveToLevelRequest {
+    pub level: u8,
+    pub transition_time: Option<u16>,
+}
+
+#[derive(FromTLV)]
+pub struct StepRequest {
+    pub step_mode: u8,
+    pub step_size: u8,
+    pub transition_time: Option<u16>,
+}
+
+#[derive(FromRepr, EnumDiscriminants)]
+#[repr(u16)]
+pub enum Attributes {
+    CurrentLevel(AttrType<Option<u8>>) = 0x0,
+}
+
+attribute_enum!(Attributes);
+
+pub const CURRENT_LEVEL: Attribute = Attribute::new(
+    AttributesDiscriminants::CurrentLevel as _,
+    Access::RV,
+    Quality::from_bits(Quality::NULLABLE.bits() | Quality::PERSISTENT.bits()).unwrap(),
+);
+
+pub const CLUSTER: Cluster<'static> = Cluster {
+    id: ID as _,
+    feature_map: 0,
+    attributes: &[FEATURE_MAP, ATTRIBUTE_LIST, CURRENT_LEVEL],
+    commands: &[CMD_MOVE_TO_LEVEL, CMD_MOVE, CMD_STEP, CMD_STOP],
+};
+
+pub struct LevelControlCluster {
+    data_ver: Dataver,
+    level: Cell<u8>,
+}
+
+impl LevelControlCluster {
+    pub const fn new(data_ver: Dataver) -> Self {
+        Self {
+            data_ver,
+            level: Cell::new(254),
+        }
+    }
+
+    pub fn current_level(&self) -> u8 {
+        self.level.get()
+    }
+
+    pub fn set_level(&self, level: u8) {
+        if self.level.get() != level {
+            self.level.set(level);
+            self.data_ver.changed();
+        }
+    }
+
+    pub fn read(
+        &self,
+        _exchange: &Exchange,
+        attr: &AttrDetails,
+        encoder: AttrDataEncoder,
+    ) -> Result<(), Error> {
+        if let Some(writer) = encoder.with_dataver(self.data_ver.get())? {
+            if attr.is_system() {
+                CLUSTER.read(attr.attr_id, writer)
+            } else {
+                match attr.attr_id.try_into()? {
+                    Attributes::CurrentLevel(codec) => {
+                        codec.encode(writer, Some(self.level.get()))
+                    }
+                }
+            }
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn invoke(
+        &self,
+        _exchange: &Exchange,
+        cmd: &CmdDetails,
+        data: &TLVElement,
+        _encoder: CmdDataEncoder,
+    ) -> Result<(), Error> {
+        match cmd.cmd_id {
+            CMD_MOVE_TO_LEVEL => {
+                let request = MoveToLevelRequest::from_tlv(data)?;
+                self.set_level(request.level);
+            }
+            CMD_STEP => {
+                let request = StepRequest::from_tlv(data)?;
+                let step = request.step_size as i16 * if request.step_mode == 0 { 1 } else { -1 };
+                let level = (self.level.get() as i16 + step).clamp(0, 254) as u8;
+                self.set_level(level);
+            }
+            CMD_STOP => {
+                // Nothing to stop: we never start a running transition in the first place.
+            }
+            CMD_MOVE => {
+                // `Move` (ramp at a rate until `Stop`) needs a running transition task we don't
+                // have. Silently accepting it would report success to the controller for a level
+                // change that never happens, so reject it instead of pretending to animate.
+                Err(ErrorCode::InvalidCommand)?
+            }
+            _ => Err(ErrorCode::CommandNotFound)?,
+        }
+
+        Ok(())
+    }
+}
+
+impl Handler for LevelControlCluster {
+    fn read(
+        &self,
+        exchange: &Exchange,
+        attr: &AttrDetails,
+        encoder: AttrDataEncoder,
+    ) -> Result<(), Error> {
+        LevelControlCluster::read(self, exchange, attr, encoder)
+    }
+
+    fn invoke(
+        &self,
+        exchange: &Exchange,
+        cmd: &CmdDetails,
+        data: &TLVElement,
+        encoder: CmdDataEncoder,
+    ) -> Result<(), Error> {
+        LevelControlCluster::invoke(self, exchange, cmd, data, encoder)
+    }
+}
+
+impl NonBlockingHandler for LevelControlCluster {}
+
+impl ChangeNotifier<()> for LevelControlCluster {
+    fn consume_change(&mut self) -> Option<()> {
+        self.data_ver.consume_change(())
+    }
+}
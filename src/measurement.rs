@@ -0,0 +1,255 @@
+/*
+*
+*    Copyright (c) 2020-2022 Project CHIP Authors
+*
+*    Licensed under the Apache License, Version 2.0 (the "License");
+*    you may not use this file except in compliance with the License.
+*    You may obtain a copy of the License at
+*
+*        http://www.apache.org/licenses/LICENSE-2.0
+*
+*    Unless required by applicable law or agreed to in writing, software
+*    distributed under the License is distributed on an "AS IS" BASIS,
+*    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*    See the License for the specific language governing permissions and
+*    limitations under the License.
+*/
+
+//! A generic Matter measurement cluster (`MeasuredValue`/`MinMeasuredValue`/`MaxMeasuredValue`),
+//! shared by `temperature_measurement`, `humidity_measurement` and `pressure_measurement`.
+//!
+//! Unlike three near-identical hand-written clusters, `MeasurementCluster<S>` tracks the
+//! observed min/max across the device's lifetime, persists the last measurement (and the
+//! min/max) to NVS so they survive a reboot, and only bumps the `Dataver` once a new reading
+//! moves by more than `S::DEAD_BAND` from the last reported value, to cut down on subscription
+//! report churn. `S: MeasurementSpec` supplies the per-cluster id, raw wire representation and
+//! the float<->raw conversion.
+//!
+//! Because the raw wire representation (`i16` for temperature/pressure, `u16` for humidity)
+//! differs per cluster, this module reads/writes attributes by matching on `attr.attr_id`
+//! directly rather than through the `attribute_enum!`/`strum` machinery the single-purpose
+//! clusters used, since that machinery isn't generic-friendly.
+
+use std::cell::Cell;
+
+use rs_matter::data_model::objects::{Access, AttrType, Attribute, Cluster, Handler, Quality};
+use rs_matter::error::{Error, ErrorCode};
+use rs_matter::transport::exchange::Exchange;
+
+use rs_matter::data_model::objects::{
+    AttrDataEncoder, AttrDetails, ChangeNotifier, Dataver, NonBlockingHandler, ATTRIBUTE_LIST,
+    FEATURE_MAP,
+};
+
+use esp_idf_svc::nvs::{EspNvs, NvsDefault};
+
+const ATTR_MEASURED_VALUE: u16 = 0x0;
+const ATTR_MIN_MEASURED_VALUE: u16 = 0x1;
+const ATTR_MAX_MEASURED_VALUE: u16 = 0x2;
+
+pub const MEASURED_VALUE: Attribute = Attribute::new(
+    ATTR_MEASURED_VALUE,
+    Access::RV,
+    Quality::from_bits(Quality::NULLABLE.bits() | Quality::PERSISTENT.bits()).unwrap(),
+);
+pub const MIN_MEASURED_VALUE: Attribute = Attribute::new(
+    ATTR_MIN_MEASURED_VALUE,
+    Access::RV,
+    Quality::from_bits(Quality::NULLABLE.bits() | Quality::PERSISTENT.bits()).unwrap(),
+);
+pub const MAX_MEASURED_VALUE: Attribute = Attribute::new(
+    ATTR_MAX_MEASURED_VALUE,
+    Access::RV,
+    Quality::from_bits(Quality::NULLABLE.bits() | Quality::PERSISTENT.bits()).unwrap(),
+);
+
+/// A 2-byte little-endian wire/NVS representation for the raw measurement repr (`i16`/`u16`).
+pub trait RawCodec: Copy + PartialEq + PartialOrd {
+    fn to_le_bytes(self) -> [u8; 2];
+    fn from_le_bytes(bytes: [u8; 2]) -> Self;
+}
+
+impl RawCodec for i16 {
+    fn to_le_bytes(self) -> [u8; 2] {
+        i16::to_le_bytes(self)
+    }
+
+    fn from_le_bytes(bytes: [u8; 2]) -> Self {
+        i16::from_le_bytes(bytes)
+    }
+}
+
+impl RawCodec for u16 {
+    fn to_le_bytes(self) -> [u8; 2] {
+        u16::to_le_bytes(self)
+    }
+
+    fn from_le_bytes(bytes: [u8; 2]) -> Self {
+        u16::from_le_bytes(bytes)
+    }
+}
+
+/// Describes one concrete measurement: its cluster id, its raw wire repr, the float<->raw
+/// conversion and the dead-band (in the same units as `set()`'s `f32`) below which a new
+/// reading does not bump the `Dataver`.
+pub trait MeasurementSpec {
+    type Raw: RawCodec + 'static;
+
+    const ID: u32;
+    const DEAD_BAND: f32;
+    /// NVS key prefix for this measurement; kept short since NVS keys are capped at 15 bytes
+    /// and this module appends `_v`/`_mn`/`_mx`.
+    const NVS_KEY_PREFIX: &'static str;
+
+    fn to_raw(value: f32) -> Self::Raw;
+    fn from_raw(raw: Self::Raw) -> f32;
+}
+
+pub struct MeasurementCluster<S: MeasurementSpec> {
+    data_ver: Dataver,
+    current: Cell<Option<f32>>,
+    min_raw: Cell<Option<S::Raw>>,
+    max_raw: Cell<Option<S::Raw>>,
+    nvs: EspNvs<NvsDefault>,
+}
+
+impl<S: MeasurementSpec> MeasurementCluster<S> {
+    pub const fn cluster() -> Cluster<'static> {
+        Cluster {
+            id: S::ID as _,
+            feature_map: 0,
+            attributes: &[
+                FEATURE_MAP,
+                ATTRIBUTE_LIST,
+                MEASURED_VALUE,
+                MIN_MEASURED_VALUE,
+                MAX_MEASURED_VALUE,
+            ],
+            commands: &[],
+        }
+    }
+
+    /// Loads the last persisted measurement (and min/max) from `nvs`, if any, so a reboot
+    /// doesn't reset the reported history.
+    pub fn new(data_ver: Dataver, nvs: EspNvs<NvsDefault>) -> Self {
+        let current = Self::load_raw(&nvs, "_v").map(S::from_raw);
+        let min_raw = Self::load_raw(&nvs, "_mn");
+        let max_raw = Self::load_raw(&nvs, "_mx");
+
+        Self {
+            data_ver,
+            current: Cell::new(current),
+            min_raw: Cell::new(min_raw),
+            max_raw: Cell::new(max_raw),
+            nvs,
+        }
+    }
+
+    pub fn get(&self) -> Option<f32> {
+        self.current.get()
+    }
+
+    /// Records a new reading. Min/max are updated unconditionally (so the lifetime extremes
+    /// are never missed), but the `Dataver` (and thus subscription reports) only advance when
+    /// the reading moves by more than `S::DEAD_BAND` from the last reported value, or when a
+    /// presence/absence transition occurs (`Some` <-> `None`).
+    pub fn set(&self, value: Option<f32>) {
+        let significant_change = match (self.current.get(), value) {
+            (Some(last), Some(value)) => (value - last).abs() > S::DEAD_BAND,
+            (last, value) => last.is_some() != value.is_some(),
+        };
+
+        if let Some(value) = value {
+            let raw = S::to_raw(value);
+            if self.min_raw.get().is_none_or(|min| raw < min) {
+                self.min_raw.set(Some(raw));
+                self.store_raw("_mn", raw);
+            }
+            if self.max_raw.get().is_none_or(|max| raw > max) {
+                self.max_raw.set(Some(raw));
+                self.store_raw("_mx", raw);
+            }
+        }
+
+        if significant_change {
+            self.current.set(value);
+            match value {
+                Some(value) => self.store_raw("_v", S::to_raw(value)),
+                None => self.clear_raw("_v"),
+            }
+            self.data_ver.changed();
+        }
+    }
+
+    fn load_raw(nvs: &EspNvs<NvsDefault>, suffix: &str) -> Option<S::Raw> {
+        let key = Self::nvs_key(suffix);
+        let mut buf = [0u8; 2];
+        match nvs.get_raw(&key, &mut buf) {
+            Ok(Some(bytes)) if bytes.len() == 2 => Some(S::Raw::from_le_bytes([bytes[0], bytes[1]])),
+            _ => None,
+        }
+    }
+
+    fn store_raw(&self, suffix: &str, raw: S::Raw) {
+        let key = Self::nvs_key(suffix);
+        let _ = self.nvs.set_raw(&key, &raw.to_le_bytes());
+    }
+
+    fn clear_raw(&self, suffix: &str) {
+        let key = Self::nvs_key(suffix);
+        let _ = self.nvs.remove(&key);
+    }
+
+    fn nvs_key(suffix: &str) -> heapless::String<15> {
+        let mut key = heapless::String::new();
+        let _ = key.push_str(S::NVS_KEY_PREFIX);
+        let _ = key.push_str(suffix);
+        key
+    }
+
+    pub fn read(
+        &self,
+        _exchange: &Exchange,
+        attr: &AttrDetails,
+        encoder: AttrDataEncoder,
+    ) -> Result<(), Error> {
+        if let Some(writer) = encoder.with_dataver(self.data_ver.get())? {
+            if attr.is_system() {
+                Self::cluster().read(attr.attr_id, writer)
+            } else {
+                match attr.attr_id {
+                    ATTR_MEASURED_VALUE => AttrType::<Option<S::Raw>>::new()
+                        .encode(writer, self.current.get().map(S::to_raw)),
+                    ATTR_MIN_MEASURED_VALUE => {
+                        AttrType::<Option<S::Raw>>::new().encode(writer, self.min_raw.get())
+                    }
+                    ATTR_MAX_MEASURED_VALUE => {
+                        AttrType::<Option<S::Raw>>::new().encode(writer, self.max_raw.get())
+                    }
+                    _ => Err(ErrorCode::AttributeNotFound.into()),
+                }
+            }
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<S: MeasurementSpec> Handler for MeasurementCluster<S> {
+    fn read(
+        &self,
+        exchange: &Exchange,
+        attr: &AttrDetails,
+        encoder: AttrDataEncoder,
+    ) -> Result<(), Error> {
+        MeasurementCluster::read(self, exchange, attr, encoder)
+    }
+}
+
+impl<S: MeasurementSpec> NonBlockingHandler for MeasurementCluster<S> {}
+
+impl<S: MeasurementSpec> ChangeNotifier<()> for MeasurementCluster<S> {
+    fn consume_change(&mut self) -> Option<()> {
+        self.data_ver.consume_change(())
+    }
+}
@@ -0,0 +1,534 @@
+/*
+*
+*    Copyright (c) 2020-2022 Project CHIP Authors
+*
+*    Licensed under the Apache License, Version 2.0 (the "License");
+*    you may not use this file except in compliance with the License.
+*    You may obtain a copy of the License at
+*
+*        http://www.apache.org/licenses/LICENSE-2.0
+*
+*    Unless required by applicable law or agreed to in writing, software
+*    distributed under the License is distributed on an "AS IS" BASIS,
+*    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*    See the License for the specific language governing permissions and
+*    limitations under the License.
+*/
+
+//! The OTA Software Update **Requestor** cluster (`0x002A` -- not to be confused with the
+//! Provider cluster, `0x0029`, which this device does not implement) plus a flash-backed
+//! updater that streams a downloaded image into the inactive OTA app partition.
+//!
+//! `AnnounceOTAProvider` is the only command a Requestor serves: `QueryImage` and the BDX
+//! transfer itself are *sent by* the requestor to the announced provider, which lives in the
+//! `rs-matter`/`esp-idf-matter` exchange/BDX transport, not in this cluster. What belongs here
+//! -- and is wired all the way through, not left as inert scaffolding -- is: recording the
+//! announced provider, and [`OtaRequestorCluster::run_update`] driving [`FlashUpdater`] off an
+//! [`OtaImageSource`] (the seam a real BDX client plugs into) to actually stream bytes into
+//! flash and request a boot-partition switch.
+
+use std::cell::Cell;
+
+use embedded_storage::nor_flash::{NorFlash, ReadNorFlash};
+
+use rs_matter::attribute_enum;
+use rs_matter::data_model::objects::{Access, AttrType, Attribute, Cluster, Handler, Quality};
+use rs_matter::error::{Error, ErrorCode};
+use rs_matter::tlv::{FromTLV, TLVElement, ToTLV};
+use rs_matter::transport::exchange::Exchange;
+
+use rs_matter::data_model::objects::{
+    AttrDataEncoder, AttrDetails, ChangeNotifier, CmdDataEncoder, CmdDetails, Dataver,
+    NonBlockingHandler, ATTRIBUTE_LIST, FEATURE_MAP,
+};
+
+use strum::{EnumDiscriminants, FromRepr};
+
+pub const ID: u32 = 0x002a;
+
+pub const CMD_ANNOUNCE_OTA_PROVIDER: u16 = 0x00;
+
+/// `AnnounceOTAProviderRequest`, trimmed to the fields this requestor actually needs to start
+/// a query: which provider to ask, and on which of its endpoints.
+#[derive(FromTLV)]
+pub struct AnnounceOtaProviderRequest {
+    pub provider_node_id: u64,
+    pub vendor_id: u16,
+    pub announcement_reason: u8,
+    pub endpoint: u16,
+}
+
+/// The provider most recently recorded for this requestor by `AnnounceOTAProvider`, which
+/// `DefaultOTAProviders` reports back (at most one entry).
+///
+/// The real `DefaultOTAProviders` attribute is `list[OTAProviderLocation]`, fabric-scoped, and
+/// writable; a standards-compliant controller writes a TLV array there. Parsing and storing a
+/// full fabric-scoped list is out of scope for this minimal requestor, so `DefaultOTAProviders`
+/// is exposed read-only here (reporting at most the one provider we know about) and
+/// `AnnounceOTAProvider` is the only supported way to configure a provider.
+#[derive(ToTLV, Clone, Copy, PartialEq)]
+pub struct ProviderLocation {
+    pub provider_node_id: u64,
+    pub endpoint: u16,
+}
+
+#[derive(FromRepr, EnumDiscriminants)]
+#[repr(u16)]
+pub enum Attributes {
+    DefaultOTAProviders(AttrType<Option<ProviderLocation>>) = 0x0,
+    UpdateState(AttrType<u8>) = 0x2,
+    UpdateStateProgress(AttrType<Option<u8>>) = 0x3,
+}
+
+attribute_enum!(Attributes);
+
+// Read-only: see the `ProviderLocation` doc comment for why this doesn't accept the spec's
+// list-typed write.
+pub const DEFAULT_OTA_PROVIDERS: Attribute = Attribute::new(
+    AttributesDiscriminants::DefaultOTAProviders as _,
+    Access::RV,
+    Quality::NONE,
+);
+pub const UPDATE_STATE: Attribute =
+    Attribute::new(AttributesDiscriminants::UpdateState as _, Access::RV, Quality::NONE);
+pub const UPDATE_STATE_PROGRESS: Attribute = Attribute::new(
+    AttributesDiscriminants::UpdateStateProgress as _,
+    Access::RV,
+    Quality::from_bits(Quality::NULLABLE.bits()).unwrap(),
+);
+
+pub const CLUSTER: Cluster<'static> = Cluster {
+    id: ID as _,
+    feature_map: 0,
+    attributes: &[
+        FEATURE_MAP,
+        ATTRIBUTE_LIST,
+        DEFAULT_OTA_PROVIDERS,
+        UPDATE_STATE,
+        UPDATE_STATE_PROGRESS,
+    ],
+    commands: &[CMD_ANNOUNCE_OTA_PROVIDER],
+};
+
+/// Mirrors the `OTAUpdateStateEnum` values relevant to a requestor: where the device currently
+/// is in the announce/query/download/apply sequence.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum OtaUpdateState {
+    Unknown = 0,
+    Idle = 1,
+    Querying = 2,
+    DelayedOnQuery = 3,
+    Downloading = 4,
+    Applying = 5,
+    DelayedOnApply = 6,
+    RollingBack = 7,
+}
+
+/// Pulls the next chunk of the OTA image as it streams in over BDX. This is the seam the BDX
+/// client (owned by the `rs-matter`/`esp-idf-matter` exchange layer, not this crate) plugs
+/// into; [`OtaRequestorCluster::run_update`] only knows how to drain one.
+pub trait OtaImageSource {
+    /// Returns the next chunk (and, on the first successful call, the total image length if
+    /// known), or `Ok(None)` once the transfer is complete.
+    fn next_chunk(&mut self, buf: &mut [u8]) -> Result<Option<(usize, Option<u32>)>, Error>;
+}
+
+pub struct OtaRequestorCluster {
+    data_ver: Dataver,
+    state: Cell<OtaUpdateState>,
+    progress: Cell<Option<u8>>,
+    provider: Cell<Option<ProviderLocation>>,
+    update_requested: Cell<bool>,
+}
+
+impl OtaRequestorCluster {
+    pub const fn new(data_ver: Dataver) -> Self {
+        Self {
+            data_ver,
+            state: Cell::new(OtaUpdateState::Idle),
+            progress: Cell::new(None),
+            provider: Cell::new(None),
+            update_requested: Cell::new(false),
+        }
+    }
+
+    pub fn state(&self) -> OtaUpdateState {
+        self.state.get()
+    }
+
+    pub fn set_state(&self, state: OtaUpdateState) {
+        if self.state.get() != state {
+            self.state.set(state);
+            self.data_ver.changed();
+        }
+    }
+
+    /// Updates `UpdateStateProgress` (0-100, or `None` while not downloading), bumping the
+    /// `Dataver` so subscribers see the BDX transfer advance.
+    pub fn set_progress(&self, progress: Option<u8>) {
+        if self.progress.get() != progress {
+            self.progress.set(progress);
+            self.data_ver.changed();
+        }
+    }
+
+    /// Returns the provider `AnnounceOTAProvider` asked us to query, clearing the request flag,
+    /// so the device loop can drive exactly one [`run_update`](Self::run_update) per
+    /// announcement rather than re-triggering on every poll.
+    pub fn take_requested_update(&self) -> Option<ProviderLocation> {
+        if self.update_requested.replace(false) {
+            self.provider.get()
+        } else {
+            None
+        }
+    }
+
+    /// Drains `source` into `flash`, tracking `UpdateStateProgress` as it goes, then verifies
+    /// the image and asks ESP-IDF (via `activate_boot_partition`) to boot it next reset.
+    pub fn run_update(
+        &self,
+        flash: &mut FlashUpdater<impl NorFlash>,
+        source: &mut impl OtaImageSource,
+        activate_boot_partition: impl FnOnce() -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        self.set_state(OtaUpdateState::Downloading);
+        flash.reset();
+
+        let mut buf = [0u8; 512];
+        let result = loop {
+            match source.next_chunk(&mut buf) {
+                Ok(Some((len, total_len))) => {
+                    if let Err(e) = flash.write_chunk(&buf[..len], total_len) {
+                        break Err(e);
+                    }
+                    self.set_progress(flash.progress_percent());
+                }
+                Ok(None) => break Ok(()),
+                Err(e) => break Err(e),
+            }
+        };
+
+        if let Err(e) = result {
+            self.set_state(OtaUpdateState::DelayedOnQuery);
+            self.set_progress(None);
+            return Err(e);
+        }
+
+        self.set_state(OtaUpdateState::Applying);
+        match flash.verify_and_activate(activate_boot_partition) {
+            Ok(()) => {
+                self.set_state(OtaUpdateState::DelayedOnApply);
+                Ok(())
+            }
+            Err(e) => {
+                self.set_state(OtaUpdateState::DelayedOnQuery);
+                self.set_progress(None);
+                Err(e)
+            }
+        }
+    }
+
+    pub fn read(
+        &self,
+        _exchange: &Exchange,
+        attr: &AttrDetails,
+        encoder: AttrDataEncoder,
+    ) -> Result<(), Error> {
+        if let Some(writer) = encoder.with_dataver(self.data_ver.get())? {
+            if attr.is_system() {
+                CLUSTER.read(attr.attr_id, writer)
+            } else {
+                match attr.attr_id.try_into()? {
+                    Attributes::DefaultOTAProviders(codec) => {
+                        codec.encode(writer, self.provider.get())
+                    }
+                    Attributes::UpdateState(codec) => codec.encode(writer, self.state.get() as u8),
+                    Attributes::UpdateStateProgress(codec) => {
+                        codec.encode(writer, self.progress.get())
+                    }
+                }
+            }
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn invoke(
+        &self,
+        _exchange: &Exchange,
+        cmd: &CmdDetails,
+        data: &TLVElement,
+        _encoder: CmdDataEncoder,
+    ) -> Result<(), Error> {
+        match cmd.cmd_id {
+            CMD_ANNOUNCE_OTA_PROVIDER => {
+                let request = AnnounceOtaProviderRequest::from_tlv(data)?;
+                self.provider.set(Some(ProviderLocation {
+                    provider_node_id: request.provider_node_id,
+                    endpoint: request.endpoint,
+                }));
+                let _ = request.vendor_id;
+                let _ = request.announcement_reason;
+
+                self.update_requested.set(true);
+                self.set_state(OtaUpdateState::Querying);
+
+                Ok(())
+            }
+            _ => Err(ErrorCode::CommandNotFound.into()),
+        }
+    }
+}
+
+impl Handler for OtaRequestorCluster {
+    fn read(
+        &self,
+        exchange: &Exchange,
+        attr: &AttrDetails,
+        encoder: AttrDataEncoder,
+    ) -> Result<(), Error> {
+        OtaRequestorCluster::read(self, exchange, attr, encoder)
+    }
+
+    fn invoke(
+        &self,
+        exchange: &Exchange,
+        cmd: &CmdDetails,
+        data: &TLVElement,
+        encoder: CmdDataEncoder,
+    ) -> Result<(), Error> {
+        OtaRequestorCluster::invoke(self, exchange, cmd, data, encoder)
+    }
+}
+
+impl NonBlockingHandler for OtaRequestorCluster {}
+
+impl ChangeNotifier<()> for OtaRequestorCluster {
+    fn consume_change(&mut self) -> Option<()> {
+        self.data_ver.consume_change(())
+    }
+}
+
+/// Minimum plausible image size: at least enough bytes to hold the ESP-IDF app image header's
+/// magic byte, checked by [`FlashUpdater::verify_and_activate`] before activating anything.
+const IMAGE_HEADER_LEN: usize = 8;
+
+/// The first byte of every ESP-IDF app image (`esp_image_header_t::magic`).
+const ESP_IMAGE_MAGIC: u8 = 0xe9;
+
+/// Streams a BDX image transfer into the inactive OTA app partition of a `NorFlash` device,
+/// erasing ahead of the write cursor one sector at a time.
+///
+/// `F` is the raw flash partition (typically [`OtaPartitionFlash`]); `sector_size` must match
+/// the device's erase granularity.
+pub struct FlashUpdater<F> {
+    flash: F,
+    sector_size: u32,
+    partition_size: u32,
+    write_cursor: u32,
+    erased_up_to: u32,
+    expected_len: Option<u32>,
+}
+
+impl<F: NorFlash> FlashUpdater<F> {
+    pub fn new(flash: F, partition_size: u32, sector_size: u32) -> Self {
+        Self {
+            flash,
+            sector_size,
+            partition_size,
+            write_cursor: 0,
+            erased_up_to: 0,
+            expected_len: None,
+        }
+    }
+
+    /// Resets the cursor for a fresh transfer (e.g. after `AnnounceOTAProvider` restarts the
+    /// query/download sequence).
+    pub fn reset(&mut self) {
+        self.write_cursor = 0;
+        self.erased_up_to = 0;
+        self.expected_len = None;
+    }
+
+    pub fn progress_percent(&self) -> Option<u8> {
+        self.expected_len.map(|len| {
+            if len == 0 {
+                100
+            } else {
+                ((self.write_cursor as u64 * 100) / len as u64) as u8
+            }
+        })
+    }
+
+    /// Appends one BDX chunk to the partition, erasing further sectors as the write cursor
+    /// reaches them. `total_len`, when known from the BDX transfer init, is recorded so
+    /// [`Self::verify_and_activate`] can check the final write cursor against it.
+    pub fn write_chunk(&mut self, chunk: &[u8], total_len: Option<u32>) -> Result<(), Error> {
+        if let Some(total_len) = total_len {
+            self.expected_len = Some(total_len);
+        }
+
+        let end = self
+            .write_cursor
+            .checked_add(chunk.len() as u32)
+            .filter(|&end| end <= self.partition_size)
+            .ok_or(ErrorCode::NoSpace)?;
+
+        while self.erased_up_to < end {
+            self.flash
+                .erase(self.erased_up_to, self.erased_up_to + self.sector_size)
+                .map_err(|_| ErrorCode::Invalid)?;
+            self.erased_up_to += self.sector_size;
+        }
+
+        self.flash
+            .write(self.write_cursor, chunk)
+            .map_err(|_| ErrorCode::Invalid)?;
+        self.write_cursor = end;
+
+        Ok(())
+    }
+
+    /// Verifies the transfer completed at the expected length and that the image we just wrote
+    /// actually starts with an ESP-IDF app image header, then asks ESP-IDF to boot the partition
+    /// on next reset.
+    ///
+    /// Refuses to activate an image that is smaller than the header (can only be a truncated or
+    /// corrupt transfer) or whose first byte isn't the ESP-IDF app image magic -- BDX chunk
+    /// metadata is supplied by whatever sent the image, so checking the bytes we actually wrote
+    /// catches a well-formed-looking but wrong transfer that length bookkeeping alone would miss.
+    pub fn verify_and_activate(
+        &mut self,
+        activate_boot_partition: impl FnOnce() -> Result<(), Error>,
+    ) -> Result<(), Error>
+    where
+        F: ReadNorFlash,
+    {
+        let written = self.write_cursor as usize;
+        if written < IMAGE_HEADER_LEN {
+            return Err(ErrorCode::InvalidData.into());
+        }
+
+        if let Some(expected_len) = self.expected_len {
+            if self.write_cursor != expected_len {
+                return Err(ErrorCode::InvalidData.into());
+            }
+        }
+
+        let mut magic = [0u8; 1];
+        self.flash
+            .read(0, &mut magic)
+            .map_err(|_| ErrorCode::Invalid)?;
+        if magic[0] != ESP_IMAGE_MAGIC {
+            return Err(ErrorCode::InvalidData.into());
+        }
+
+        activate_boot_partition()
+    }
+}
+
+/// The inactive ESP-IDF OTA app partition, as a raw `embedded-storage` `NorFlash` device, plus
+/// the `esp_ota_set_boot_partition` call that makes a freshly-written image bootable.
+///
+/// `Clone`/`Copy` since this is just a handle to ESP-IDF's own partition table entry: cloning
+/// it to keep a handle around after the original is moved into a [`FlashUpdater`] doesn't open
+/// a second, independent view of the flash.
+#[derive(Clone, Copy)]
+pub struct OtaPartitionFlash {
+    partition: *const esp_idf_sys::esp_partition_t,
+}
+
+// The `esp_partition_t*` only ever gets read by ESP-IDF's partition driver; the device loop
+// that owns this is single-threaded, same as every other peripheral handle in `main.rs`.
+unsafe impl Send for OtaPartitionFlash {}
+
+impl OtaPartitionFlash {
+    /// Finds the OTA app partition that is *not* currently running -- the one
+    /// `esp_ota_set_boot_partition` will later mark bootable -- to stream the new image into.
+    pub fn next_update_partition() -> Result<Self, Error> {
+        let partition = unsafe { esp_idf_sys::esp_ota_get_next_update_partition(core::ptr::null()) };
+        if partition.is_null() {
+            return Err(ErrorCode::NoSpace.into());
+        }
+
+        Ok(Self { partition })
+    }
+
+    pub fn size(&self) -> u32 {
+        unsafe { (*self.partition).size }
+    }
+
+    /// Marks this partition bootable on next reset. Must only be called after
+    /// [`FlashUpdater::verify_and_activate`] has confirmed a complete, correctly-sized image.
+    pub fn set_boot_partition(&self) -> Result<(), Error> {
+        let result = unsafe { esp_idf_sys::esp_ota_set_boot_partition(self.partition) };
+        if result == 0 {
+            Ok(())
+        } else {
+            Err(ErrorCode::Invalid.into())
+        }
+    }
+}
+
+impl ReadNorFlash for OtaPartitionFlash {
+    type Error = embedded_storage::nor_flash::NorFlashErrorKind;
+
+    const READ_SIZE: usize = 1;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        let result = unsafe {
+            esp_idf_sys::esp_partition_read(
+                self.partition,
+                offset as usize,
+                bytes.as_mut_ptr() as *mut core::ffi::c_void,
+                bytes.len(),
+            )
+        };
+
+        if result == 0 {
+            Ok(())
+        } else {
+            Err(embedded_storage::nor_flash::NorFlashErrorKind::Other)
+        }
+    }
+
+    fn capacity(&self) -> usize {
+        self.size() as usize
+    }
+}
+
+impl NorFlash for OtaPartitionFlash {
+    // ESP-IDF flash writes must be 4-byte aligned; erases happen in 4KiB sectors.
+    const WRITE_SIZE: usize = 4;
+    const ERASE_SIZE: usize = 4096;
+
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        let result = unsafe {
+            esp_idf_sys::esp_partition_erase_range(self.partition, from, (to - from) as usize)
+        };
+
+        if result == 0 {
+            Ok(())
+        } else {
+            Err(embedded_storage::nor_flash::NorFlashErrorKind::Other)
+        }
+    }
+
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        let result = unsafe {
+            esp_idf_sys::esp_partition_write(
+                self.partition,
+                offset as usize,
+                bytes.as_ptr() as *const core::ffi::c_void,
+                bytes.len(),
+            )
+        };
+
+        if result == 0 {
+            Ok(())
+        } else {
+            Err(embedded_storage::nor_flash::NorFlashErrorKind::Other)
+        }
+    }
+}
@@ -0,0 +1,47 @@
+/*
+*
+*    Copyright (c) 2020-2022 Project CHIP Authors
+*
+*    Licensed under the Apache License, Version 2.0 (the "License");
+*    you may not use this file except in compliance with the License.
+*    You may obtain a copy of the License at
+*
+*        http://www.apache.org/licenses/LICENSE-2.0
+*
+*    Unless required by applicable law or agreed to in writing, software
+*    distributed under the License is distributed on an "AS IS" BASIS,
+*    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*    See the License for the specific language governing permissions and
+*    limitations under the License.
+*/
+
+//! The Pressure Measurement cluster (`0x0403`), specialized from the generic
+//! [`crate::measurement::MeasurementCluster`]. `MeasuredValue` is hPa*10, backed by a QMP6988
+//! barometric sensor (see [`crate::qmp6988`]).
+
+use crate::measurement::{MeasurementCluster, MeasurementSpec};
+
+pub const ID: u32 = 0x0403;
+pub const CLUSTER: rs_matter::data_model::objects::Cluster<'static> =
+    MeasurementCluster::<PressureSpec>::cluster();
+
+pub struct PressureSpec;
+
+impl MeasurementSpec for PressureSpec {
+    type Raw = i16;
+
+    const ID: u32 = ID;
+    // A tenth of an hPa: below this, a new reading isn't worth a report.
+    const DEAD_BAND: f32 = 0.1;
+    const NVS_KEY_PREFIX: &'static str = "pres";
+
+    fn to_raw(value: f32) -> i16 {
+        (value * 10.0).round() as i16
+    }
+
+    fn from_raw(raw: i16) -> f32 {
+        raw as f32 / 10.0
+    }
+}
+
+pub type PressureMeasurementCluster = MeasurementCluster<PressureSpec>;